@@ -1,45 +1,116 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Which desktop shortcuts the generated NSIS installer should clean up
+/// after a successful install. Read from `[package.metadata.shabtzak.installer]`
+/// in `Cargo.toml`, e.g.:
+///
+/// ```toml
+/// [package.metadata.shabtzak.installer]
+/// remove-shortcuts = ["api-server.lnk", "api-server.exe.lnk", "Uninstall Shabtzak.lnk", "resources.lnk"]
+/// ```
+///
+/// Listing the names there means adding or renaming a sidecar only needs a
+/// metadata change. If no metadata (or an empty list) is present, falls back
+/// to the fixed list of shortcuts Tauri/NSIS currently generate.
+struct ShortcutPolicy {
+    remove: Vec<String>,
+}
+
+const DEFAULT_REMOVE: &[&str] = &[
+    "api-server.lnk",
+    "api-server.exe.lnk",
+    "Uninstall Shabtzak.lnk",
+    "resources.lnk",
+];
+
+fn default_remove() -> Vec<String> {
+    DEFAULT_REMOVE.iter().map(|s| s.to_string()).collect()
+}
+
+fn load_shortcut_policy(manifest_dir: &Path) -> ShortcutPolicy {
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(manifest_dir.join("Cargo.toml"))
+        .no_deps()
+        .exec();
+
+    let remove = metadata
+        .ok()
+        .and_then(|m| m.root_package().cloned())
+        .and_then(|pkg| pkg.metadata.get("shabtzak").cloned())
+        .and_then(|v| v.get("installer").cloned())
+        .and_then(|v| v.get("remove-shortcuts").cloned())
+        .and_then(|v| v.as_array().cloned())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect::<Vec<_>>()
+        })
+        .filter(|names| !names.is_empty())
+        .unwrap_or_else(default_remove);
+
+    ShortcutPolicy { remove }
+}
+
+/// Discovers every `installer.nsi` Tauri generated, across all built
+/// architectures (`x64`, `arm64`, ...) rather than assuming `x64`.
+fn find_installer_nsis(manifest_dir: &Path) -> Vec<PathBuf> {
+    let nsis_root = manifest_dir.join("target").join("release").join("nsis");
+    let Ok(entries) = fs::read_dir(&nsis_root) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path().join("installer.nsi"))
+        .filter(|path| path.exists())
+        .collect()
+}
+
+/// Patches the NSIS installer to remove unwanted desktop shortcuts
+/// (only keeping the main app shortcut) after a successful install.
+fn patch_installer(path: &Path, policy: &ShortcutPolicy) {
+    let Ok(content) = fs::read_to_string(path) else {
+        return;
+    };
+
+    // Skip if already patched
+    if content.contains("Custom cleanup: Remove unwanted shortcuts") {
+        return;
+    }
+
+    let deletes: String = policy
+        .remove
+        .iter()
+        .map(|name| format!("  Delete \"$DESKTOP\\{}\"\n", name))
+        .collect();
+    let cleanup = format!(
+        "\n  ; Custom cleanup: Remove unwanted shortcuts (see [package.metadata.shabtzak.installer] in Cargo.toml)\n{}",
+        deletes
+    );
+
+    // Find .onInstSuccess and add cleanup before FunctionEnd
+    if let Some(pos) = content.find("  run_done:") {
+        if let Some(end_pos) = content[pos..].find("FunctionEnd") {
+            let insert_pos = pos + end_pos;
+            let mut new_content = content.clone();
+            new_content.insert_str(insert_pos, &cleanup);
+            let _ = fs::write(path, new_content);
+        }
+    }
+}
+
 fn main() {
     tauri_build::build();
-    
-    // Patch NSIS installer to remove unwanted desktop shortcuts
-    // This runs after Tauri generates the installer.nsi but before it's compiled
-    use std::fs;
-    use std::path::PathBuf;
-    
-    // Try to find the installer.nsi that was just generated
-    // Tauri generates it in target/release/nsis/x64/installer.nsi
-    if let Ok(cargo_manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
-        let installer_nsi = PathBuf::from(cargo_manifest_dir)
-            .join("target")
-            .join("release")
-            .join("nsis")
-            .join("x64")
-            .join("installer.nsi");
-        
-        if installer_nsi.exists() {
-            if let Ok(content) = fs::read_to_string(&installer_nsi) {
-                // Skip if already patched
-                if !content.contains("Custom cleanup: Remove unwanted shortcuts") {
-                    // Add cleanup code to .onInstSuccess function
-                    let cleanup = r#"
-  ; Custom cleanup: Remove unwanted shortcuts (only keep Shabtzak app)
-  Delete "$DESKTOP\api-server.lnk"
-  Delete "$DESKTOP\Uninstall Shabtzak.lnk"
-  Delete "$DESKTOP\resources.lnk"
-  Delete "$DESKTOP\api-server.exe.lnk"
-"#;
-                    
-                    // Find .onInstSuccess and add cleanup before FunctionEnd
-                    if let Some(pos) = content.find("  run_done:") {
-                        if let Some(end_pos) = content[pos..].find("FunctionEnd") {
-                            let insert_pos = pos + end_pos;
-                            let mut new_content = content.clone();
-                            new_content.insert_str(insert_pos, cleanup);
-                            let _ = fs::write(&installer_nsi, new_content);
-                        }
-                    }
-                }
-            }
+
+    // Patch every generated NSIS installer to remove unwanted desktop
+    // shortcuts. Runs after Tauri generates installer.nsi but before it's
+    // compiled for each target architecture.
+    if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+        let manifest_dir = PathBuf::from(manifest_dir);
+        let policy = load_shortcut_policy(&manifest_dir);
+        for installer_nsi in find_installer_nsis(&manifest_dir) {
+            patch_installer(&installer_nsi, &policy);
         }
     }
 }
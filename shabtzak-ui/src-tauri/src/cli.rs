@@ -0,0 +1,53 @@
+// Command-line overrides for the defaults the host app would otherwise
+// derive on its own (app-local data dir, DATABASE_URL, sidecar bind address).
+// Useful for running the Tauri shell against a separately started Python
+// backend during development.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(name = "shabtzak", about = "Shabtzak desktop app")]
+pub struct Cli {
+    /// Override the app-local data directory (defaults to the OS app_data_dir)
+    #[arg(long, value_name = "DIR")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Override the SQLite database path (defaults to `<data-dir>/shabtzak.db`)
+    #[arg(long, value_name = "PATH")]
+    pub db_path: Option<PathBuf>,
+
+    /// Host the sidecar backend should bind to
+    #[arg(long, value_name = "HOST", default_value = "127.0.0.1")]
+    pub backend_host: String,
+
+    /// Port the sidecar backend should bind to
+    #[arg(long, value_name = "PORT", default_value_t = 8000)]
+    pub backend_port: u16,
+
+    /// Skip spawning the bundled sidecar and talk to this backend URL instead
+    #[arg(long, value_name = "URL")]
+    pub external_backend: Option<String>,
+}
+
+impl Cli {
+    /// Parses `std::env::args()`, but never aborts the process the way
+    /// `Cli::parse()` would on an unrecognized argument. macOS Launch
+    /// Services passes a `-psn_xxxx` argument to bundled apps opened via
+    /// Finder/double-click, which clap doesn't know about; falling back to
+    /// defaults instead of exiting keeps a normal GUI launch working.
+    pub fn parse_args() -> Self {
+        let args = std::env::args().filter(|a| !a.starts_with("-psn_"));
+
+        Cli::try_parse_from(args).unwrap_or_else(|e| {
+            eprintln!("Ignoring unrecognized command-line arguments: {}", e);
+            Cli::default_args()
+        })
+    }
+
+    fn default_args() -> Self {
+        let binary = std::env::args().next().unwrap_or_default();
+        Cli::parse_from(std::iter::once(binary))
+    }
+}
@@ -0,0 +1,168 @@
+// Structured logging for the Tauri host process and the sidecar output it
+// forwards. Writes to a rotating `backend.log` file and keeps a small
+// in-memory tail so the frontend can show recent lines without re-reading
+// the file from disk.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Local;
+use log::{Level, Log, Metadata, Record};
+
+const DEFAULT_MAX_RECENT_LINES: usize = 2000;
+
+struct RotatingFileLogger {
+    path: PathBuf,
+    max_bytes: u64,
+    max_backups: u32,
+    writer: Mutex<BufWriter<File>>,
+    recent: Mutex<VecDeque<String>>,
+}
+
+impl RotatingFileLogger {
+    fn new(path: PathBuf, max_bytes: u64, max_backups: u32) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            max_bytes,
+            max_backups,
+            writer: Mutex::new(BufWriter::new(file)),
+            recent: Mutex::new(VecDeque::with_capacity(DEFAULT_MAX_RECENT_LINES)),
+        })
+    }
+
+    fn rotate_if_needed(&self, writer: &mut BufWriter<File>) {
+        let size = match writer.get_ref().metadata() {
+            Ok(meta) => meta.len(),
+            Err(_) => return,
+        };
+        if size < self.max_bytes {
+            return;
+        }
+
+        let _ = writer.flush();
+
+        // Drop the active file handle before renaming: on Windows, renaming
+        // a file that's still open (without FILE_SHARE_DELETE) fails. Swap
+        // in a throwaway writer to the null device first so the real handle
+        // actually closes.
+        *writer = BufWriter::new(null_file());
+
+        for index in (1..self.max_backups).rev() {
+            let from = backup_path(&self.path, index);
+            let to = backup_path(&self.path, index + 1);
+            if from.exists() {
+                if let Err(e) = fs::rename(&from, &to) {
+                    eprintln!("Failed to rotate log backup {}: {}", from.display(), e);
+                }
+            }
+        }
+        if let Err(e) = fs::rename(&self.path, backup_path(&self.path, 1)) {
+            eprintln!("Failed to rotate log file {}: {}", self.path.display(), e);
+        }
+
+        match OpenOptions::new().create(true).append(true).open(&self.path) {
+            Ok(file) => *writer = BufWriter::new(file),
+            Err(e) => eprintln!("Failed to reopen log file {} after rotation: {}", self.path.display(), e),
+        }
+    }
+
+    fn push_recent(&self, line: String) {
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() >= DEFAULT_MAX_RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+}
+
+/// Opens the OS null device, used as a placeholder writer while the active
+/// log file handle is closed for rotation.
+fn null_file() -> File {
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    OpenOptions::new()
+        .write(true)
+        .open(null_path)
+        .expect("failed to open null device")
+}
+
+fn backup_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+impl Log for RotatingFileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} [{}] {}",
+            Local::now().to_rfc3339(),
+            record.level(),
+            record.args()
+        );
+
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{}", line) {
+            eprintln!("Failed to write to log file: {}", e);
+        }
+        let _ = writer.flush();
+        self.rotate_if_needed(&mut writer);
+        drop(writer);
+
+        self.push_recent(line);
+    }
+
+    fn flush(&self) {
+        let _ = self.writer.lock().unwrap().flush();
+    }
+}
+
+static LOGGER: OnceLock<&'static RotatingFileLogger> = OnceLock::new();
+
+/// Initialize the global logger to write `path`, rotating to `path.1`,
+/// `path.2`, ... up to `max_backups` once the active file exceeds `max_bytes`.
+pub fn init(path: PathBuf, max_bytes: u64, max_backups: u32) -> std::io::Result<()> {
+    let logger = Box::leak(Box::new(RotatingFileLogger::new(path, max_bytes, max_backups)?));
+    let _ = LOGGER.set(logger);
+    log::set_logger(logger)
+        .map(|()| log::set_max_level(log::LevelFilter::Info))
+        .unwrap_or_else(|e| eprintln!("Logger already initialized: {}", e));
+    Ok(())
+}
+
+/// Best-effort severity sniffed out of a line of sidecar (Uvicorn/Python)
+/// output, e.g. `"ERROR:    Exception in ASGI application"`.
+pub fn parse_severity(line: &str) -> Level {
+    let upper = line.trim_start();
+    if upper.starts_with("ERROR") || upper.starts_with("CRITICAL") {
+        Level::Error
+    } else if upper.starts_with("WARNING") || upper.starts_with("WARN") {
+        Level::Warn
+    } else if upper.starts_with("DEBUG") {
+        Level::Debug
+    } else {
+        Level::Info
+    }
+}
+
+/// Returns up to the last `lines` log lines currently held in memory, for
+/// the frontend's in-app diagnostics panel.
+pub fn recent_logs(lines: usize) -> Vec<String> {
+    match LOGGER.get() {
+        Some(logger) => {
+            let recent = logger.recent.lock().unwrap();
+            recent.iter().rev().take(lines).rev().cloned().collect()
+        }
+        None => Vec::new(),
+    }
+}
@@ -2,139 +2,192 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use tauri::Manager;
-use tauri::api::process::{Command, CommandEvent};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use regex::Regex;
 
-fn strip_ansi_codes(text: &str) -> String {
+mod cli;
+mod crypto;
+mod logging;
+mod scheme;
+mod supervisor;
+
+use cli::Cli;
+
+const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const LOG_MAX_BACKUPS: u32 = 5;
+
+pub(crate) fn strip_ansi_codes(text: &str) -> String {
     // Remove ANSI escape sequences (color codes)
     let re = Regex::new(r"\x1b\[[0-9;]*m").unwrap_or_else(|_| Regex::new("").unwrap());
     re.replace_all(text, "").to_string()
 }
 
+/// Injects the backend's base URL into the webview so the frontend's API
+/// client can point at it regardless of which port/host it ended up on.
+pub(crate) fn inject_backend_url(window: &tauri::Window, base_url: &str) {
+    let _ = window.eval(&format!(
+        "window.__BACKEND_URL__ = '{}'; \
+        if (typeof localStorage !== 'undefined') {{ \
+            localStorage.setItem('backend_url', '{}'); \
+        }} \
+        if (window.updateApiBaseURL) {{ \
+            window.updateApiBaseURL('{}'); \
+        }}",
+        base_url, base_url, base_url
+    ));
+}
+
+/// Returns up to the last `lines` log lines this process has recorded (the
+/// logger's in-memory tail, not a read of the log file) for the frontend's
+/// in-app diagnostics panel.
+#[tauri::command]
+fn get_recent_logs(lines: usize) -> Vec<String> {
+    logging::recent_logs(lines)
+}
+
 fn main() {
-    tauri::Builder::default()
-        .setup(|app| {
+    let cli = Cli::parse_args();
+    let backend_port = Arc::new(Mutex::new(cli.backend_port));
+
+    scheme::register(tauri::Builder::default())
+        .manage(scheme::BackendPort(backend_port.clone()))
+        .setup(move |app| {
             // Compute an app-local SQLite path and start backend sidecar if available
             let app_handle = app.handle();
-            let app_dir = tauri::api::path::app_data_dir(&app_handle.config())
-                .unwrap_or_else(|| std::env::temp_dir().join("shabtzak"));
+            let app_dir = cli.data_dir.clone().unwrap_or_else(|| {
+                tauri::api::path::app_data_dir(&app_handle.config())
+                    .unwrap_or_else(|| std::env::temp_dir().join("shabtzak"))
+            });
             let _ = std::fs::create_dir_all(&app_dir);
 
-            let db_path = app_dir.join("shabtzak.db");
-            
-            // On first launch, copy the bundled database to user's app data directory
-            // This ensures all users get the initial data with Hebrew soldier names
-            if !db_path.exists() {
-                if let Some(resource_dir) = app_handle.path_resolver().resource_dir() {
-                    let resource_path = resource_dir.join("shabtzak.db");
-                    if resource_path.exists() {
-                        if let Err(e) = std::fs::copy(&resource_path, &db_path) {
-                            eprintln!("Failed to copy initial database: {}", e);
-                        } else {
-                            println!("[Shabtzak] Initialized database from bundle");
+            let db_path = cli.db_path.clone().unwrap_or_else(|| app_dir.join("shabtzak.db"));
+
+            // Log app data directory location for debugging
+            let log_file_path = app_dir.join("backend.log");
+            if let Err(e) = logging::init(log_file_path.clone(), LOG_MAX_BYTES, LOG_MAX_BACKUPS) {
+                eprintln!("Failed to initialize logger: {}", e);
+            }
+            log::info!("App data directory: {}", app_dir.display());
+            log::info!("Database: {}", db_path.display());
+            log::info!("Log file: {}", log_file_path.display());
+
+            // Derive (or load) the per-install encryption key up front: it's
+            // needed both to validate an existing encrypted database and to
+            // encrypt a freshly re-seeded one below.
+            let db_key = crypto::get_or_create_key(&app_dir).map_err(|e| {
+                log::error!("Failed to obtain database encryption key: {}", e);
+                e
+            })?;
+
+            // Re-seed from the bundled database if it's missing, empty, or a
+            // structurally broken plaintext file (e.g. a truncated copy or
+            // crash mid-write) — `check_database` runs `integrity_check`
+            // before trusting a plaintext header. A non-plaintext file that
+            // fails to open under `db_key` is NOT re-seeded: SQLCipher can't
+            // tell a wrong/lost key apart from a corrupt encrypted file, and
+            // guessing wrong would silently discard the user's real data, so
+            // that case is a hard startup error instead (see below).
+            match crypto::check_database(&db_path, &db_key) {
+                crypto::DatabaseStatus::Valid => {}
+                crypto::DatabaseStatus::Unreadable => {
+                    log::error!(
+                        "Database at {} could not be opened with the stored encryption key; refusing to start. \
+                         This means either the key was lost/changed or the encrypted file is corrupt \
+                         (SQLCipher can't tell these apart without the correct key) \u{2014} restore the key \
+                         or move the file aside manually and restart to re-seed.",
+                        db_path.display()
+                    );
+                    return Err(format!(
+                        "database at {} is unreadable with the current key",
+                        db_path.display()
+                    )
+                    .into());
+                }
+                crypto::DatabaseStatus::Missing => {
+                    if db_path.exists() {
+                        let timestamp = std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .map(|d| d.as_secs())
+                            .unwrap_or(0);
+                        let quarantine_path =
+                            app_dir.join(format!("shabtzak.db.corrupt-{}", timestamp));
+                        log::error!(
+                            "Database at {} is missing, empty, or corrupt; moving it to {} and re-seeding",
+                            db_path.display(),
+                            quarantine_path.display()
+                        );
+                        let _ = std::fs::rename(&db_path, &quarantine_path);
+                    }
+
+                    if let Some(resource_dir) = app_handle.path_resolver().resource_dir() {
+                        let resource_path = resource_dir.join("shabtzak.db");
+                        if resource_path.exists() {
+                            if let Err(e) = std::fs::copy(&resource_path, &db_path) {
+                                log::error!("Failed to copy initial database: {}", e);
+                            } else {
+                                log::info!("Initialized database from bundle");
+                            }
                         }
                     }
                 }
             }
-            
+
             let database_url = format!(
                 "sqlite:///{}",
                 db_path.to_string_lossy().replace('\\', "/")
             );
-            
-            // Log app data directory location for debugging
-            println!("[Shabtzak] App data directory: {}", app_dir.display());
-            println!("[Shabtzak] Database: {}", db_path.display());
-            println!("[Shabtzak] Log file: {}\\backend.log", app_dir.display());
-
-            // Try to spawn a bundled sidecar named "api-server"
-            let mut cmd = Command::new_sidecar("api-server")
-                .map_err(|e| {
-                    eprintln!("No sidecar found: {}", e);
+            if let Err(e) = crypto::ensure_encrypted(&db_path, &db_key) {
+                log::error!(
+                    "Failed to encrypt database at {}: {} (refusing to start against an unencrypted or partially migrated file)",
+                    db_path.display(),
                     e
-                })?;
+                );
+                return Err(e.into());
+            }
+
+            // An external backend skips the sidecar entirely: just point the
+            // frontend at the given URL once the window is ready.
+            if let Some(external_url) = cli.external_backend.clone() {
+                log::info!("Using external backend: {}", external_url);
+                if let Some(window) = app_handle.get_window("main") {
+                    inject_backend_url(&window, &external_url);
+                }
+                return Ok(());
+            }
 
+            // The sidecar-backed case always uses the stable `shabtzak://api`
+            // origin, which the scheme bridge forwards to whichever port the
+            // sidecar is currently bound to (see `supervisor::run`) — inject
+            // it once up front instead of eval-injecting the raw, changing
+            // `http://localhost:<port>` origin on every restart.
+            if let Some(window) = app_handle.get_window("main") {
+                inject_backend_url(&window, &format!("{}://api", scheme::SCHEME));
+            }
+
+            // Spawn the "api-server" sidecar under a supervisor that restarts
+            // it with backoff on crash and keeps the frontend informed via
+            // `backend-status` events.
             let mut envs = HashMap::new();
             envs.insert("DATABASE_URL".to_string(), database_url);
-            cmd = cmd.envs(envs)
-                .args(["--host", "127.0.0.1", "--port", "8000"]);
+            envs.insert("DATABASE_KEY".to_string(), db_key);
 
-            let (mut rx, _child) = cmd.spawn().map_err(|e| {
-                eprintln!("Failed to start backend sidecar: {}", e);
-                e
-            })?;
-
-            // Capture backend port and inject it into the frontend
-            // Also write logs to a file for debugging
-            let log_file_path = app_dir.join("backend.log");
-            let log_file_path_clone = log_file_path.clone();
+            let supervisor_cfg = supervisor::SidecarConfig {
+                sidecar_name: "api-server",
+                host: cli.backend_host.clone(),
+                port: cli.backend_port,
+                envs,
+                port_state: backend_port.clone(),
+            };
             let app_handle = app_handle.clone();
-            tauri::async_runtime::spawn(async move {
-                use std::fs::OpenOptions;
-                use std::io::Write;
-                
-                // Open log file with UTF-8 encoding
-                use std::io::BufWriter;
-                let log_file = OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(&log_file_path_clone)
-                    .unwrap_or_else(|e| {
-                        eprintln!("Failed to open log file: {}", e);
-                        panic!("Cannot open log file");
-                    });
-                let mut log_writer = BufWriter::new(log_file);
-                
-                let mut backend_port = 8000u16;
-                while let Some(event) = rx.recv().await {
-                    match event {
-                        CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
-                            eprintln!("[api] {}", line);
-                            // Strip ANSI color codes and write as UTF-8
-                            let cleaned_line = strip_ansi_codes(&line);
-                            if let Err(e) = writeln!(log_writer, "[api] {}", cleaned_line) {
-                                eprintln!("Failed to write to log file: {}", e);
-                            }
-                            let _ = log_writer.flush();
-                            
-                            // Parse port from "Uvicorn running on http://127.0.0.1:8001"
-                            if line.contains("Uvicorn running on") {
-                                if let Some(port_str) = line
-                                    .split("http://127.0.0.1:")
-                                    .nth(1)
-                                    .and_then(|s| s.split_whitespace().next())
-                                {
-                                    if let Ok(port) = port_str.parse::<u16>() {
-                                        backend_port = port;
-                                        let port_url = format!("http://localhost:{}", port);
-                                        // Inject API URL into frontend via window (store in localStorage and update API)
-                                        if let Some(window) = app_handle.get_window("main") {
-                                            let _ = window.eval(&format!(
-                                                "window.__BACKEND_URL__ = '{}'; \
-                                                if (typeof localStorage !== 'undefined') {{ \
-                                                    localStorage.setItem('backend_url', '{}'); \
-                                                }} \
-                                                if (window.updateApiBaseURL) {{ \
-                                                    window.updateApiBaseURL('{}'); \
-                                                }}",
-                                                port_url, port_url, port_url
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            });
+            tauri::async_runtime::spawn(supervisor::run(app_handle, supervisor_cfg));
 
             Ok(())
         })
         .on_window_event(|event| {
             let _ = event;
         })
+        .invoke_handler(tauri::generate_handler![get_recent_logs])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
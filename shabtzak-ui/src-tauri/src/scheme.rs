@@ -0,0 +1,80 @@
+// A `shabtzak://api/...` custom URI scheme that forwards to the sidecar on
+// `127.0.0.1:<backend_port>`. This gives the frontend a stable origin to
+// call regardless of which port the sidecar ended up bound to, instead of
+// `window.eval`-injecting the raw `http://localhost:<port>` origin.
+
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+use tauri::http::ResponseBuilder;
+use tauri::Manager;
+
+pub const SCHEME: &str = "shabtzak";
+
+/// Authorities the scheme bridge is allowed to forward; anything else is
+/// rejected rather than silently proxied, mirroring Tauri's asset protocol
+/// scoping. `shabtzak://api/...` is the only bridge the frontend uses.
+const ALLOWED_AUTHORITIES: &[&str] = &["api"];
+
+/// Shared, live backend port, updated by the sidecar supervisor whenever it
+/// (re)detects the port Uvicorn bound to.
+pub struct BackendPort(pub Arc<Mutex<u16>>);
+
+pub fn register(builder: tauri::Builder<tauri::Wry>) -> tauri::Builder<tauri::Wry> {
+    builder.register_uri_scheme_protocol(SCHEME, |app, request| {
+        let port = *app.state::<BackendPort>().0.lock().unwrap();
+
+        let url = match url::Url::parse(request.uri()) {
+            Ok(url) => url,
+            Err(e) => {
+                log::error!("Malformed shabtzak:// request {}: {}", request.uri(), e);
+                return ResponseBuilder::new().status(400).body(Vec::new());
+            }
+        };
+
+        let authority = url.host_str().unwrap_or("");
+        if !ALLOWED_AUTHORITIES.contains(&authority) {
+            log::error!("Rejected shabtzak:// request outside the allowlist: {}", request.uri());
+            return ResponseBuilder::new().status(403).body(Vec::new());
+        }
+
+        let mut target = format!("http://127.0.0.1:{}{}", port, url.path());
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+
+        let mut outgoing = ureq::request(request.method().as_str(), &target);
+        for (name, value) in request.headers().iter() {
+            if let Ok(value) = value.to_str() {
+                outgoing = outgoing.set(name.as_str(), value);
+            }
+        }
+
+        let body = request.body();
+        let result = if body.is_empty() {
+            outgoing.call()
+        } else {
+            outgoing.send_bytes(body)
+        };
+
+        match result {
+            Ok(resp) => {
+                let status = resp.status();
+                let mut builder = ResponseBuilder::new().status(status);
+                for header_name in resp.headers_names() {
+                    if let Some(value) = resp.header(&header_name) {
+                        builder = builder.header(header_name, value);
+                    }
+                }
+                let mut bytes = Vec::new();
+                let _ = resp.into_reader().read_to_end(&mut bytes);
+                builder.body(bytes)
+            }
+            Err(e) => {
+                log::error!("shabtzak:// bridge request to {} failed: {}", target, e);
+                ResponseBuilder::new().status(502).body(Vec::new())
+            }
+        }
+    })
+}
@@ -0,0 +1,137 @@
+// Supervises the `api-server` sidecar: restarts it with exponential backoff
+// if it exits, and tells the frontend what's going on via `backend-status`
+// events so it can show a reconnecting banner instead of failing silently.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::api::process::{Command, CommandEvent};
+use tauri::Manager;
+
+use crate::{logging, strip_ansi_codes};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const STABILITY_WINDOW: Duration = Duration::from_secs(20);
+const MAX_ATTEMPTS: u32 = 10;
+
+pub struct SidecarConfig {
+    pub sidecar_name: &'static str,
+    pub host: String,
+    pub port: u16,
+    pub envs: HashMap<String, String>,
+    /// Shared with the `shabtzak://` scheme bridge so it always forwards to
+    /// whichever port the sidecar most recently bound to.
+    pub port_state: Arc<Mutex<u16>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum BackendState {
+    Starting,
+    Running,
+    Crashed,
+    Restarting,
+}
+
+#[derive(Clone, Serialize)]
+struct BackendStatus {
+    state: BackendState,
+    port: u16,
+}
+
+fn emit_status(app_handle: &tauri::AppHandle, state: BackendState, port: u16) {
+    let _ = app_handle.emit_all("backend-status", BackendStatus { state, port });
+}
+
+fn build_command(cfg: &SidecarConfig) -> tauri::api::Result<Command> {
+    let cmd = Command::new_sidecar(cfg.sidecar_name)?
+        .envs(cfg.envs.clone())
+        .args(["--host", &cfg.host, "--port", &cfg.port.to_string()]);
+    Ok(cmd)
+}
+
+/// Runs until `MAX_ATTEMPTS` consecutive quick crashes are hit, or until the
+/// sidecar cannot be found or spawned (that returns at once, with no retry),
+/// spawning the sidecar, watching it for port announcements and exit, and
+/// respawning with exponential backoff when it dies.
+pub async fn run(app_handle: tauri::AppHandle, cfg: SidecarConfig) {
+    let mut attempt: u32 = 0;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut port = cfg.port;
+
+    loop {
+        emit_status(&app_handle, BackendState::Starting, port);
+
+        let cmd = match build_command(&cfg) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                log::error!("No sidecar found: {}", e);
+                emit_status(&app_handle, BackendState::Crashed, port);
+                return;
+            }
+        };
+
+        let (mut rx, _child) = match cmd.spawn() {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                log::error!("Failed to start backend sidecar: {}", e);
+                emit_status(&app_handle, BackendState::Crashed, port);
+                return;
+            }
+        };
+
+        let started_at = std::time::Instant::now();
+
+        loop {
+            match rx.recv().await {
+                Some(CommandEvent::Stdout(line)) | Some(CommandEvent::Stderr(line)) => {
+                    let cleaned_line = strip_ansi_codes(&line);
+                    log::log!(logging::parse_severity(&cleaned_line), "[api] {}", cleaned_line);
+
+                    if line.contains("Uvicorn running on") {
+                        if let Some(port_str) = line
+                            .split("http://127.0.0.1:")
+                            .nth(1)
+                            .and_then(|s| s.split_whitespace().next())
+                        {
+                            if let Ok(parsed_port) = port_str.parse::<u16>() {
+                                port = parsed_port;
+                                // The frontend always talks to the stable
+                                // `shabtzak://api` origin (see `scheme.rs`),
+                                // which forwards here; update the shared
+                                // state the scheme bridge reads instead of
+                                // eval-injecting the raw, changing port.
+                                *cfg.port_state.lock().unwrap() = port;
+                                emit_status(&app_handle, BackendState::Running, port);
+                            }
+                        }
+                    }
+                }
+                Some(CommandEvent::Terminated(_)) | None => break,
+                _ => {}
+            }
+        }
+
+        log::error!("Backend sidecar exited; will attempt to restart");
+        emit_status(&app_handle, BackendState::Crashed, port);
+
+        if started_at.elapsed() >= STABILITY_WINDOW {
+            attempt = 0;
+            backoff = INITIAL_BACKOFF;
+        } else {
+            attempt += 1;
+        }
+
+        if attempt >= MAX_ATTEMPTS {
+            log::error!("Backend sidecar crashed {} times in a row; giving up", attempt);
+            return;
+        }
+
+        emit_status(&app_handle, BackendState::Restarting, port);
+        tokio::time::sleep(backoff).await;
+        backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+    }
+}